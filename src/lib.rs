@@ -8,10 +8,13 @@ use std::collections::BTreeMap;
 use std::error::Error;
 use std::ffi::OsStr;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
 use rpm;
 
+pub mod repository;
+
 pub fn split_package_into_components(
     pkg_path: &Path,
     destination: Option<PathBuf>,
@@ -100,6 +103,83 @@ pub fn extract_package_payload(
     Ok(())
 }
 
+/// Write a package's payload as content-addressed blobs rather than a directory tree.
+///
+/// Every file's uncompressed contents is hashed with SHA-256 and written to
+/// `<destination>/objects/<hex digest>`, skipping the write if that digest already
+/// exists. A JSON manifest mapping each logical path to its digest, mode, and size is
+/// written alongside. Running this repeatedly across related packages (successive builds
+/// of the same RPM, say) stores each unchanged file exactly once and makes re-export
+/// incremental.
+pub fn export_package_chunked(
+    pkg_path: &Path,
+    destination: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let package = rpm::Package::open(pkg_path)?;
+    let epoch = package.metadata.get_epoch().unwrap_or(0).to_string();
+    let package_nevra = rpm::Nevra::new(
+        package.metadata.get_name()?,
+        &epoch,
+        package.metadata.get_version()?,
+        package.metadata.get_release()?,
+        package.metadata.get_arch()?,
+    );
+    let dest_path = destination.unwrap_or_else(|| PathBuf::from("."));
+    let objects_dir = dest_path.join("objects");
+    fs::create_dir_all(&objects_dir)?;
+
+    let mut manifest = serde_json::Map::new();
+    for f in package.files()? {
+        let f = f?;
+
+        let digest = sha256_hex(&f.content);
+        let object_path = objects_dir.join(&digest);
+        if !object_path.exists() {
+            write_object_atomically(&object_path, &f.content)?;
+        }
+
+        manifest.insert(
+            f.metadata.path.display().to_string(),
+            serde_json::json!({
+                "digest": digest,
+                "mode": f.metadata.mode,
+                "size": f.content.len(),
+            }),
+        );
+    }
+
+    let manifest_path = dest_path.join(format!("{}.manifest.json", package_nevra));
+    let document = serde_json::json!({
+        "nevra": package_nevra.to_string(),
+        "files": manifest,
+    });
+    fs::write(manifest_path, serde_json::to_string_pretty(&document)?)?;
+
+    Ok(())
+}
+
+/// Write an object's content to a temp file beside `object_path` and rename it into
+/// place, so a process that dies mid-write (OOM kill, disk full, SIGKILL) never leaves
+/// a truncated blob for a future run to mistake for the real thing and skip rewriting.
+fn write_object_atomically(object_path: &Path, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let tmp_path = object_path.with_extension(format!("tmp.{}", std::process::id()));
+    fs::write(&tmp_path, data)?;
+    fs::rename(&tmp_path, object_path)?;
+    Ok(())
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
 pub fn print_package_file_list(pkg_path: &Path) -> Result<(), Box<dyn Error>> {
     let package = rpm::Package::open(pkg_path)?;
 
@@ -203,3 +283,810 @@ pub fn print_package_file_tree(pkg_path: &Path) -> Result<(), Box<dyn Error>> {
     tree_display(&paths);
     Ok(())
 }
+
+/// Generate an SPDX-style licensing report covering one or more packages.
+///
+/// Each input path is either a single RPM or a directory, which is expanded via
+/// [`repository::Repository::load`]. For every package, the declared `License` tag is
+/// paired with the relative paths of payload files flagged `RPMFILE_LICENSE` - the
+/// declared tag alone doesn't tell a distributor which files to actually ship as the
+/// third-party notice, so both are reported together, keyed by package NEVRA.
+pub fn generate_license_manifest(inputs: &[PathBuf]) -> Result<(), Box<dyn Error>> {
+    let mut packages_out = serde_json::Map::new();
+
+    for pkg_path in collect_license_inputs(inputs)? {
+        let package = rpm::Package::open(&pkg_path)?;
+        let epoch = package.metadata.get_epoch().unwrap_or(0).to_string();
+        let package_nevra = rpm::Nevra::new(
+            package.metadata.get_name()?,
+            &epoch,
+            package.metadata.get_version()?,
+            package.metadata.get_release()?,
+            package.metadata.get_arch()?,
+        );
+        let license = package.metadata.get_license()?;
+
+        let mut license_files = Vec::new();
+        for f in package.files()? {
+            let f = f?;
+            if f.metadata.flags.contains(rpm::FileFlags::LICENSE) {
+                license_files.push(f.metadata.path.display().to_string());
+            }
+        }
+        license_files.sort();
+
+        packages_out.insert(
+            package_nevra.to_string(),
+            serde_json::json!({
+                "licenseDeclared": license,
+                "licenseFiles": license_files,
+            }),
+        );
+    }
+
+    let document = serde_json::json!({
+        "spdxVersion": "SPDX-2.3",
+        "packages": packages_out,
+    });
+    println!("{}", serde_json::to_string_pretty(&document)?);
+
+    Ok(())
+}
+
+/// Expand a mix of package paths and directories into a flat list of RPM paths,
+/// walking directories via [`repository::Repository`].
+fn collect_license_inputs(inputs: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut paths = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            let repository = repository::Repository::load(input)?;
+            paths.extend(repository.packages().map(|(_, path)| path.clone()));
+        } else {
+            paths.push(input.clone());
+        }
+    }
+    Ok(paths)
+}
+
+/// Payload compression codecs supported by the `recompress` subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionFormat {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl std::str::FromStr for CompressionFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(CompressionFormat::Gzip),
+            "xz" => Ok(CompressionFormat::Xz),
+            "zstd" => Ok(CompressionFormat::Zstd),
+            other => Err(format!("unsupported compression format '{other}' (expected gzip, xz, or zstd)")),
+        }
+    }
+}
+
+impl CompressionFormat {
+    fn tag_name(&self) -> &'static str {
+        match self {
+            CompressionFormat::Gzip => "gzip",
+            CompressionFormat::Xz => "xz",
+            CompressionFormat::Zstd => "zstd",
+        }
+    }
+}
+
+/// Recompress a package's cpio payload with a different codec and level, in place.
+///
+/// The existing payload is stream-decompressed, recompressed with the requested codec,
+/// and written back. Recompressing also means rewriting the `PAYLOADCOMPRESSOR`/
+/// `PAYLOADFLAGS` tags in the main header and recomputing the payload size and digest
+/// entries in the signature header (the legacy size/MD5 pair, see
+/// [`header_and_payload_size_and_md5`], and `PAYLOADDIGEST`/`PAYLOADDIGESTALGO`, which
+/// are written whether or not the package had them before). Any existing GPG signature can no longer be valid once the payload bytes
+/// change, so it is dropped rather than left in place to falsely vouch for the new
+/// content.
+pub fn recompress_package_payload(
+    pkg_path: &Path,
+    format: CompressionFormat,
+    level: u32,
+) -> Result<(), Box<dyn Error>> {
+    let mut package = rpm::Package::open(pkg_path)?;
+
+    let raw_payload = decompress_payload(&package)?;
+    let new_payload = compress_payload(&raw_payload, format, level)?;
+
+    package.metadata.header.entries.insert(
+        rpm::IndexTag::PayloadCompressor,
+        rpm::IndexData::String(format.tag_name().to_string()),
+    );
+    package.metadata.header.entries.insert(
+        rpm::IndexTag::PayloadFlags,
+        rpm::IndexData::String(level.to_string()),
+    );
+
+    package.content = new_payload;
+
+    let (header_and_payload_size, header_and_payload_md5) =
+        header_and_payload_size_and_md5(&package.metadata.header, &package.content)?;
+    package.metadata.signature.entries.insert(
+        rpm::IndexSignatureTag::Size,
+        rpm::IndexData::Int32(vec![header_and_payload_size as i32]),
+    );
+    package.metadata.signature.entries.insert(
+        rpm::IndexSignatureTag::Md5,
+        rpm::IndexData::Bin(header_and_payload_md5),
+    );
+    package.metadata.signature.entries.insert(
+        rpm::IndexSignatureTag::PayloadDigestAlgo,
+        rpm::IndexData::Int32(vec![8]), // PGPHASHALGO_SHA256
+    );
+    package.metadata.signature.entries.insert(
+        rpm::IndexSignatureTag::PayloadDigest,
+        rpm::IndexData::StringArray(vec![sha256_hex(&package.content)]),
+    );
+    package
+        .metadata
+        .signature
+        .entries
+        .remove(&rpm::IndexSignatureTag::RsaSignature);
+    package
+        .metadata
+        .signature
+        .entries
+        .remove(&rpm::IndexSignatureTag::PgpSignature);
+
+    let mut out = fs::File::create(pkg_path)?;
+    package.write(&mut out)?;
+
+    Ok(())
+}
+
+/// The legacy `SIGTAG_SIZE`/`SIGTAG_MD5` signature tags cover the serialized main header
+/// plus the payload, not the payload alone - so computing them requires re-serializing
+/// the (already-updated) header and hashing it together with the payload bytes.
+fn header_and_payload_size_and_md5(
+    header: &rpm::Header<rpm::IndexTag>,
+    payload: &[u8],
+) -> Result<(usize, Vec<u8>), Box<dyn Error>> {
+    let mut combined = Vec::new();
+    header.write(&mut combined)?;
+    combined.extend_from_slice(payload);
+
+    Ok((combined.len(), md5::compute(&combined).to_vec()))
+}
+
+fn decompress_payload(package: &rpm::Package) -> Result<Vec<u8>, Box<dyn Error>> {
+    let compressor = package
+        .metadata
+        .get_payload_compressor()
+        .unwrap_or_else(|_| "gzip".to_string());
+
+    let mut raw = Vec::new();
+    match compressor.as_str() {
+        "gzip" => {
+            flate2::read::GzDecoder::new(&package.content[..]).read_to_end(&mut raw)?;
+        }
+        "xz" => {
+            xz2::read::XzDecoder::new(&package.content[..]).read_to_end(&mut raw)?;
+        }
+        "zstd" => {
+            zstd::stream::Decoder::new(&package.content[..])?.read_to_end(&mut raw)?;
+        }
+        other => return Err(format!("unsupported existing payload compressor '{other}'").into()),
+    }
+    Ok(raw)
+}
+
+fn compress_payload(
+    raw: &[u8],
+    format: CompressionFormat,
+    level: u32,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut out = Vec::new();
+    match format {
+        CompressionFormat::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(&mut out, flate2::Compression::new(level));
+            encoder.write_all(raw)?;
+            encoder.finish()?;
+        }
+        CompressionFormat::Xz => {
+            let mut encoder = xz2::write::XzEncoder::new(&mut out, level);
+            encoder.write_all(raw)?;
+            encoder.finish()?;
+        }
+        CompressionFormat::Zstd => {
+            let mut encoder = zstd::stream::Encoder::new(&mut out, level as i32)?;
+            encoder.write_all(raw)?;
+            encoder.finish()?;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod recompress_tests {
+    use super::*;
+
+    #[test]
+    fn recompress_updates_signature_tags_consistently() {
+        let source_path =
+            std::env::temp_dir().join(format!("rpmtools-recompress-src-{}.txt", std::process::id()));
+        fs::write(&source_path, b"payload for recompress signature test\n").unwrap();
+
+        let package = rpm::PackageBuilder::new(
+            "recompress-test",
+            "1.0.0",
+            "MIT",
+            "x86_64",
+            "fixture package for recompress signature test",
+        )
+        .with_file(
+            &source_path,
+            rpm::FileOptions::new("/usr/share/doc/recompress-test/fixture.txt"),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+        fs::remove_file(&source_path).unwrap();
+
+        let tmp_path =
+            std::env::temp_dir().join(format!("rpmtools-recompress-test-{}.rpm", std::process::id()));
+        {
+            let mut file = fs::File::create(&tmp_path).unwrap();
+            package.write(&mut file).unwrap();
+        }
+
+        recompress_package_payload(&tmp_path, CompressionFormat::Zstd, 9).unwrap();
+
+        // The package must still open cleanly after rewriting.
+        let recompressed = rpm::Package::open(&tmp_path).unwrap();
+
+        let (expected_size, expected_md5) =
+            header_and_payload_size_and_md5(&recompressed.metadata.header, &recompressed.content).unwrap();
+
+        let stored_size = match recompressed.metadata.signature.entries.get(&rpm::IndexSignatureTag::Size) {
+            Some(rpm::IndexData::Int32(values)) => values[0] as usize,
+            other => panic!("expected Int32 Size tag, got {other:?}"),
+        };
+        let stored_md5 = match recompressed.metadata.signature.entries.get(&rpm::IndexSignatureTag::Md5) {
+            Some(rpm::IndexData::Bin(bytes)) => bytes.clone(),
+            other => panic!("expected Bin Md5 tag, got {other:?}"),
+        };
+        let stored_payload_digest = match recompressed
+            .metadata
+            .signature
+            .entries
+            .get(&rpm::IndexSignatureTag::PayloadDigest)
+        {
+            Some(rpm::IndexData::StringArray(values)) => values[0].clone(),
+            other => panic!("expected StringArray PayloadDigest tag, got {other:?}"),
+        };
+
+        assert_eq!(stored_size, expected_size, "signature Size must cover header+payload");
+        assert_eq!(stored_md5, expected_md5, "signature Md5 must cover header+payload");
+        assert_eq!(
+            stored_payload_digest,
+            sha256_hex(&recompressed.content),
+            "PayloadDigest must match the recompressed payload"
+        );
+
+        fs::remove_file(&tmp_path).unwrap();
+    }
+}
+
+/// Output format for the `dump` subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for DumpFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(DumpFormat::Text),
+            "json" => Ok(DumpFormat::Json),
+            other => Err(format!("unsupported dump format '{other}' (expected text or json)")),
+        }
+    }
+}
+
+/// One decoded tag: its numeric ID, RPM value type, and value, under the name it's
+/// keyed by in the dump output.
+struct DumpEntry {
+    name: String,
+    id: i32,
+    type_name: &'static str,
+    value: serde_json::Value,
+}
+
+/// Walk the lead, signature header, and main header of a package and print every tag.
+///
+/// Uses [`rpm::PackageMetadata::open`], so the payload is never read. `--format text`
+/// mimics `rpmdump`'s column alignment; `--format json` emits an object keyed by tag
+/// name, each entry carrying its numeric tag ID, RPM value type, and decoded value
+/// (ints, strings, string arrays, and base64 for binary blobs) - carrying the type
+/// alongside the value is what keeps `StringArray` and `I18NString` (both otherwise
+/// bare JSON arrays) distinguishable, which is the point of using this as a test
+/// fixture rather than just printing values. The signature and main header sections
+/// are kept separate in the output so their tags - some of which share numeric IDs
+/// across the two namespaces - aren't confused with one another. The lead isn't
+/// tag-based, so its fields are listed without an ID/type.
+pub fn dump_package_tags(pkg_path: &Path, format: DumpFormat) -> Result<(), Box<dyn Error>> {
+    let metadata = rpm::PackageMetadata::open(pkg_path)?;
+
+    let lead_entries = vec![
+        ("name".to_string(), serde_json::json!(metadata.lead.name)),
+        ("major".to_string(), serde_json::json!(metadata.lead.major)),
+        ("minor".to_string(), serde_json::json!(metadata.lead.minor)),
+        ("type".to_string(), serde_json::json!(metadata.lead.package_type)),
+        ("archnum".to_string(), serde_json::json!(metadata.lead.archnum)),
+        ("osnum".to_string(), serde_json::json!(metadata.lead.osnum)),
+        ("signature_type".to_string(), serde_json::json!(metadata.lead.signature_type)),
+    ];
+
+    let signature_entries: Vec<DumpEntry> = metadata
+        .signature
+        .index_entries
+        .iter()
+        .map(|entry| DumpEntry {
+            name: format!("{:?}", entry.tag),
+            id: entry.tag as i32,
+            type_name: index_data_type_name(&entry.data),
+            value: index_data_to_json(&entry.data),
+        })
+        .collect();
+
+    let header_entries: Vec<DumpEntry> = metadata
+        .header
+        .index_entries
+        .iter()
+        .map(|entry| DumpEntry {
+            name: format!("{:?}", entry.tag),
+            id: entry.tag as i32,
+            type_name: index_data_type_name(&entry.data),
+            value: index_data_to_json(&entry.data),
+        })
+        .collect();
+
+    match format {
+        DumpFormat::Json => {
+            let document = serde_json::json!({
+                "lead": serde_json::Map::from_iter(lead_entries),
+                "signature": tagged_entries_to_json(&signature_entries),
+                "header": tagged_entries_to_json(&header_entries),
+            });
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        }
+        DumpFormat::Text => {
+            println!("-- lead --");
+            let lead_width = lead_entries.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            for (name, value) in &lead_entries {
+                println!("{:lead_width$}  {}", name, value, lead_width = lead_width);
+            }
+            println!("-- signature header --");
+            print_dump_section_text(&signature_entries);
+            println!("-- header --");
+            print_dump_section_text(&header_entries);
+        }
+    }
+
+    Ok(())
+}
+
+fn tagged_entries_to_json(entries: &[DumpEntry]) -> serde_json::Map<String, serde_json::Value> {
+    entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.name.clone(),
+                serde_json::json!({
+                    "id": entry.id,
+                    "type": entry.type_name,
+                    "value": entry.value,
+                }),
+            )
+        })
+        .collect()
+}
+
+fn print_dump_section_text(entries: &[DumpEntry]) {
+    let name_width = entries.iter().map(|entry| entry.name.len()).max().unwrap_or(0);
+    let type_width = entries.iter().map(|entry| entry.type_name.len()).max().unwrap_or(0);
+    for entry in entries {
+        println!(
+            "{:name_width$}  {:>6}  {:type_width$}  {}",
+            entry.name,
+            entry.id,
+            entry.type_name,
+            entry.value,
+            name_width = name_width,
+            type_width = type_width,
+        );
+    }
+}
+
+fn index_data_type_name(data: &rpm::IndexData) -> &'static str {
+    match data {
+        rpm::IndexData::Null => "NULL",
+        rpm::IndexData::Char(_) => "CHAR",
+        rpm::IndexData::Int8(_) => "INT8",
+        rpm::IndexData::Int16(_) => "INT16",
+        rpm::IndexData::Int32(_) => "INT32",
+        rpm::IndexData::Int64(_) => "INT64",
+        rpm::IndexData::String(_) => "STRING",
+        rpm::IndexData::StringArray(_) => "STRING_ARRAY",
+        rpm::IndexData::I18NString(_) => "I18NSTRING",
+        rpm::IndexData::Bin(_) => "BIN",
+    }
+}
+
+fn index_data_to_json(data: &rpm::IndexData) -> serde_json::Value {
+    match data {
+        rpm::IndexData::Null => serde_json::Value::Null,
+        rpm::IndexData::Char(values) => serde_json::json!(values),
+        rpm::IndexData::Int8(values) => serde_json::json!(values),
+        rpm::IndexData::Int16(values) => serde_json::json!(values),
+        rpm::IndexData::Int32(values) => serde_json::json!(values),
+        rpm::IndexData::Int64(values) => serde_json::json!(values),
+        rpm::IndexData::String(value) => serde_json::json!(value),
+        rpm::IndexData::StringArray(values) => serde_json::json!(values),
+        rpm::IndexData::I18NString(values) => serde_json::json!(values),
+        rpm::IndexData::Bin(bytes) => serde_json::json!(base64_encode(bytes)),
+    }
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+
+        out.push(ALPHABET[(b0 >> 2) as usize] as char);
+        out.push(ALPHABET[((b0 & 0x03) << 4 | b1 >> 4) as usize] as char);
+        out.push(if chunk.len() > 1 {
+            ALPHABET[((b1 & 0x0f) << 2 | b2 >> 6) as usize] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            ALPHABET[(b2 & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+/// Rewrite a package into a deterministic normal form so that two packages built from
+/// identical inputs end up byte-identical (reproducible-build verification), and so
+/// `split`/`dump` output on a canonicalized package is stable and diffable.
+///
+/// Both the main header and the signature header have their index entries sorted by tag
+/// ID, which also normalizes the data-store region, since it's rebuilt fresh on write in
+/// entry order. When `sort_payload` is set, the cpio payload is decompressed, its
+/// entries are reordered by name with mtimes zeroed, and it's recompressed with the
+/// same codec. The signature header's size and digest tags are recomputed afterward
+/// (see [`header_and_payload_size_and_md5`]). Canonicalizing an already-canonical
+/// package is a no-op.
+pub fn canonicalize_package(pkg_path: &Path, sort_payload: bool) -> Result<(), Box<dyn Error>> {
+    let mut package = rpm::Package::open(pkg_path)?;
+
+    package.metadata.header.entries.sort_keys();
+    package.metadata.signature.entries.sort_keys();
+
+    if sort_payload {
+        let compressor = package
+            .metadata
+            .get_payload_compressor()
+            .unwrap_or_else(|_| "gzip".to_string());
+        let format: CompressionFormat = compressor.parse().unwrap_or(CompressionFormat::Gzip);
+
+        let raw_payload = decompress_payload(&package)?;
+        let canonical_cpio = canonicalize_cpio_payload(&raw_payload)?;
+        package.content = compress_payload(&canonical_cpio, format, 6)?;
+    }
+
+    let (header_and_payload_size, header_and_payload_md5) =
+        header_and_payload_size_and_md5(&package.metadata.header, &package.content)?;
+    package.metadata.signature.entries.insert(
+        rpm::IndexSignatureTag::Size,
+        rpm::IndexData::Int32(vec![header_and_payload_size as i32]),
+    );
+    package.metadata.signature.entries.insert(
+        rpm::IndexSignatureTag::Md5,
+        rpm::IndexData::Bin(header_and_payload_md5),
+    );
+    package.metadata.signature.entries.insert(
+        rpm::IndexSignatureTag::PayloadDigestAlgo,
+        rpm::IndexData::Int32(vec![8]), // PGPHASHALGO_SHA256
+    );
+    package.metadata.signature.entries.insert(
+        rpm::IndexSignatureTag::PayloadDigest,
+        rpm::IndexData::StringArray(vec![sha256_hex(&package.content)]),
+    );
+    package.metadata.signature.entries.sort_keys();
+
+    let mut out = fs::File::create(pkg_path)?;
+    package.write(&mut out)?;
+
+    Ok(())
+}
+
+/// Reorder a raw (decompressed) cpio payload's entries by name and zero their mtimes.
+fn canonicalize_cpio_payload(raw: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut entries = cpio_newc::parse(raw)?;
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cpio_newc::write(&entries))
+}
+
+/// A minimal "newc" format cpio reader/writer - just capable enough to reorder entries
+/// and zero mtimes for [`canonicalize_package`]. Not a general-purpose cpio implementation.
+mod cpio_newc {
+    use std::error::Error;
+
+    const MAGIC: &str = "070701";
+    const TRAILER_NAME: &str = "TRAILER!!!";
+    const HEADER_LEN: usize = 110;
+
+    /// Every newc header field except `mtime`, which canonicalization zeroes.
+    pub struct Entry {
+        pub name: String,
+        pub ino: u32,
+        pub mode: u32,
+        pub uid: u32,
+        pub gid: u32,
+        pub nlink: u32,
+        pub devmajor: u32,
+        pub devminor: u32,
+        pub rdevmajor: u32,
+        pub rdevminor: u32,
+        pub check: u32,
+        pub data: Vec<u8>,
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Vec<Entry>, Box<dyn Error>> {
+        let mut entries = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            if offset + HEADER_LEN > bytes.len() {
+                return Err("truncated cpio header".into());
+            }
+            let header = std::str::from_utf8(&bytes[offset..offset + HEADER_LEN])?;
+            if &header[0..6] != MAGIC {
+                return Err(format!("unrecognized cpio magic '{}'", &header[0..6]).into());
+            }
+            let field = |range: std::ops::Range<usize>| -> Result<u32, Box<dyn Error>> {
+                Ok(u32::from_str_radix(&header[range], 16)?)
+            };
+            let ino = field(6..14)?;
+            let mode = field(14..22)?;
+            let uid = field(22..30)?;
+            let gid = field(30..38)?;
+            let nlink = field(38..46)?;
+            let file_size = field(54..62)? as usize;
+            let devmajor = field(62..70)?;
+            let devminor = field(70..78)?;
+            let rdevmajor = field(78..86)?;
+            let rdevminor = field(86..94)?;
+            let name_size = field(94..102)? as usize;
+            let check = field(102..110)?;
+            offset += HEADER_LEN;
+
+            if name_size == 0 {
+                return Err("cpio entry has zero-length name".into());
+            }
+            if offset + name_size > bytes.len() {
+                return Err("cpio entry name runs past end of payload".into());
+            }
+            let name = String::from_utf8(bytes[offset..offset + name_size - 1].to_vec())?;
+            offset += name_size;
+            offset += pad4(offset);
+
+            if name == TRAILER_NAME {
+                break;
+            }
+
+            if offset + file_size > bytes.len() {
+                return Err("cpio entry data runs past end of payload".into());
+            }
+            let data = bytes[offset..offset + file_size].to_vec();
+            offset += file_size;
+            offset += pad4(offset);
+
+            entries.push(Entry {
+                name,
+                ino,
+                mode,
+                uid,
+                gid,
+                nlink,
+                devmajor,
+                devminor,
+                rdevmajor,
+                rdevminor,
+                check,
+                data,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    pub fn write(entries: &[Entry]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for entry in entries {
+            write_entry(&mut out, entry);
+        }
+        write_entry(
+            &mut out,
+            &Entry {
+                name: TRAILER_NAME.to_string(),
+                ino: 0,
+                mode: 0,
+                uid: 0,
+                gid: 0,
+                nlink: 1,
+                devmajor: 0,
+                devminor: 0,
+                rdevmajor: 0,
+                rdevminor: 0,
+                check: 0,
+                data: Vec::new(),
+            },
+        );
+        out
+    }
+
+    fn write_entry(out: &mut Vec<u8>, entry: &Entry) {
+        let name_size = entry.name.len() + 1;
+        let header = format!(
+            "{magic}{ino:08x}{mode:08x}{uid:08x}{gid:08x}{nlink:08x}{mtime:08x}{filesize:08x}{devmajor:08x}{devminor:08x}{rdevmajor:08x}{rdevminor:08x}{namesize:08x}{check:08x}",
+            magic = MAGIC,
+            ino = entry.ino,
+            mode = entry.mode,
+            uid = entry.uid,
+            gid = entry.gid,
+            nlink = entry.nlink,
+            mtime = 0,
+            filesize = entry.data.len(),
+            devmajor = entry.devmajor,
+            devminor = entry.devminor,
+            rdevmajor = entry.rdevmajor,
+            rdevminor = entry.rdevminor,
+            namesize = name_size,
+            check = entry.check,
+        );
+        out.extend_from_slice(header.as_bytes());
+        out.extend_from_slice(entry.name.as_bytes());
+        out.push(0);
+        pad_to_4(out);
+        out.extend_from_slice(&entry.data);
+        pad_to_4(out);
+    }
+
+    fn pad4(offset: usize) -> usize {
+        (4 - offset % 4) % 4
+    }
+
+    fn pad_to_4(out: &mut Vec<u8>) {
+        while out.len() % 4 != 0 {
+            out.push(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod canonicalize_tests {
+    use super::*;
+    use crate::cpio_newc::Entry;
+
+    #[test]
+    fn canonicalizing_cpio_payload_is_idempotent() {
+        let make_entry = |name: &str, data: &[u8]| Entry {
+            name: name.to_string(),
+            ino: 0,
+            mode: 0o100644,
+            uid: 0,
+            gid: 0,
+            nlink: 1,
+            devmajor: 0,
+            devminor: 0,
+            rdevmajor: 0,
+            rdevminor: 0,
+            check: 0,
+            data: data.to_vec(),
+        };
+        let entries = vec![make_entry("b.txt", b"b"), make_entry("a.txt", b"a")];
+        let raw = cpio_newc::write(&entries);
+
+        let once = canonicalize_cpio_payload(&raw).unwrap();
+        let twice = canonicalize_cpio_payload(&once).unwrap();
+
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn canonicalize_package_is_idempotent() {
+        let package = rpm::PackageBuilder::new(
+            "canon-test",
+            "1.0.0",
+            "MIT",
+            "x86_64",
+            "fixture package for canonicalize idempotency test",
+        )
+        .build()
+        .unwrap();
+
+        let tmp_path = std::env::temp_dir().join(format!("rpmtools-canon-test-{}.rpm", std::process::id()));
+        {
+            let mut file = fs::File::create(&tmp_path).unwrap();
+            package.write(&mut file).unwrap();
+        }
+
+        canonicalize_package(&tmp_path, false).unwrap();
+        let once = fs::read(&tmp_path).unwrap();
+
+        canonicalize_package(&tmp_path, false).unwrap();
+        let twice = fs::read(&tmp_path).unwrap();
+
+        fs::remove_file(&tmp_path).unwrap();
+
+        assert_eq!(once, twice, "canonicalizing an already-canonical package must be a no-op");
+    }
+
+    #[test]
+    fn canonicalize_package_with_sort_payload_is_idempotent() {
+        let source_path =
+            std::env::temp_dir().join(format!("rpmtools-canon-src-{}.txt", std::process::id()));
+        fs::write(&source_path, b"hello from the canonicalize fixture\n").unwrap();
+
+        let package = rpm::PackageBuilder::new(
+            "canon-test-payload",
+            "1.0.0",
+            "MIT",
+            "x86_64",
+            "fixture package for canonicalize --sort-payload idempotency test",
+        )
+        .with_file(
+            &source_path,
+            rpm::FileOptions::new("/usr/share/doc/canon-test-payload/fixture.txt"),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+
+        fs::remove_file(&source_path).unwrap();
+
+        let tmp_path =
+            std::env::temp_dir().join(format!("rpmtools-canon-payload-test-{}.rpm", std::process::id()));
+        {
+            let mut file = fs::File::create(&tmp_path).unwrap();
+            package.write(&mut file).unwrap();
+        }
+
+        canonicalize_package(&tmp_path, true).unwrap();
+        let once = fs::read(&tmp_path).unwrap();
+
+        canonicalize_package(&tmp_path, true).unwrap();
+        let twice = fs::read(&tmp_path).unwrap();
+
+        fs::remove_file(&tmp_path).unwrap();
+
+        assert_eq!(
+            once, twice,
+            "canonicalizing an already-canonical package with --sort-payload must be a no-op"
+        );
+    }
+}
@@ -26,6 +26,12 @@ enum Subcommands {
     Extract(ExtractArgs),
     List(ListArgs),
     Tree(TreeArgs),
+    Index(IndexArgs),
+    ExportChunked(ExportChunkedArgs),
+    License(LicenseArgs),
+    Recompress(RecompressArgs),
+    Dump(DumpArgs),
+    Canonicalize(CanonicalizeArgs),
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
@@ -71,6 +77,75 @@ struct TreeArgs {
     input: PathBuf,
 }
 
+#[derive(FromArgs, PartialEq, Debug)]
+/// Write a package's payload out as content-addressed blobs, deduplicated by content.
+#[argh(subcommand, name = "export-chunked")]
+struct ExportChunkedArgs {
+    #[argh(positional)]
+    /// the path to the RPM taken as input
+    input: PathBuf,
+    #[argh(option)]
+    /// where to write the object store and manifest
+    destination: Option<PathBuf>,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Generate an SPDX/copyright manifest from one or more packages.
+#[argh(subcommand, name = "license")]
+struct LicenseArgs {
+    #[argh(positional)]
+    /// paths to RPMs or directories of RPMs taken as input
+    inputs: Vec<PathBuf>,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Recompress a package's payload with a different codec and level.
+#[argh(subcommand, name = "recompress")]
+struct RecompressArgs {
+    #[argh(positional)]
+    /// the path to the RPM taken as input; rewritten in place
+    input: PathBuf,
+    #[argh(option)]
+    /// the target payload compressor: gzip, xz, or zstd
+    format: rpmtools::CompressionFormat,
+    #[argh(option)]
+    /// the compressor's level
+    level: u32,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Print every tag in a package's lead, signature header, and main header (rpmdump).
+#[argh(subcommand, name = "dump")]
+struct DumpArgs {
+    #[argh(positional)]
+    /// the path to the RPM taken as input
+    input: PathBuf,
+    #[argh(option, default = "rpmtools::DumpFormat::Text")]
+    /// output format: text or json
+    format: rpmtools::DumpFormat,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Rewrite a package into a deterministic normal form for reproducible-build verification.
+#[argh(subcommand, name = "canonicalize")]
+struct CanonicalizeArgs {
+    #[argh(positional)]
+    /// the path to the RPM taken as input; rewritten in place
+    input: PathBuf,
+    #[argh(switch)]
+    /// also re-pack the cpio payload with entries sorted by name and mtimes zeroed
+    sort_payload: bool,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Index a directory tree of RPMs and print the resolved collection.
+#[argh(subcommand, name = "index")]
+struct IndexArgs {
+    #[argh(positional)]
+    /// the directory to recursively search for RPMs
+    input: PathBuf,
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let args: TopLevel = argh::from_env();
 
@@ -83,11 +158,25 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
         Subcommands::List(args) => rpmtools::print_package_file_list(&args.input)?,
         Subcommands::Tree(args) => rpmtools::print_package_file_tree(&args.input)?,
+        Subcommands::License(args) => rpmtools::generate_license_manifest(&args.inputs)?,
+        Subcommands::Recompress(args) => {
+            rpmtools::recompress_package_payload(&args.input, args.format, args.level)?
+        }
+        Subcommands::Dump(args) => rpmtools::dump_package_tags(&args.input, args.format)?,
+        Subcommands::Canonicalize(args) => {
+            rpmtools::canonicalize_package(&args.input, args.sort_payload)?
+        }
+        Subcommands::ExportChunked(args) => {
+            rpmtools::export_package_chunked(&args.input, args.destination)?
+        }
+        Subcommands::Index(args) => {
+            let repository = rpmtools::repository::Repository::load(&args.input)?;
+            for (nevra, path) in repository.packages() {
+                println!("{}\t{}", nevra, path.display());
+            }
+        }
         // TODO:
-        // * print package tags and such i.e. rpmdump
         // * rpmsort
-        // * recompress package payload
-        // * canonicalize RPM (sort tags, etc.)
     }
 
     Ok(())
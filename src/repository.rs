@@ -0,0 +1,275 @@
+// Copyright (c) 2025 Daniel Alley
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+use rpm::Nevra;
+
+/// A queryable collection of RPMs discovered under a directory tree, indexed by NEVRA.
+///
+/// Build one with [`Repository::load`], then look packages up by name or by a version
+/// constraint rather than re-walking the filesystem for every query.
+pub struct Repository {
+    packages: BTreeMap<Nevra, PathBuf>,
+}
+
+/// A constraint on package version used by [`Repository::find_by_version`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionConstraint {
+    Less,
+    LessEq,
+    Equal,
+    GreaterEq,
+    Greater,
+}
+
+impl Repository {
+    /// Recursively walk `path`, parse every `*.rpm` found, and index it by NEVRA.
+    ///
+    /// Packages are opened in parallel via rayon, with one worker thread per CPU.
+    /// Worker count and open-file count are deliberately separate: the thread pool is
+    /// sized for compute parallelism, while a semaphore sized from the process's
+    /// RLIMIT_NOFILE bounds how many packages may be open at once, so indexing a
+    /// directory with thousands of RPMs can't exhaust file descriptors regardless of
+    /// how many CPUs the pool has to work with.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let rpm_paths = collect_rpm_paths(path)?;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(available_parallelism())
+            .build()?;
+
+        let fd_budget = FdBudget::new(max_concurrent_opens());
+
+        let results: Vec<Result<(Nevra, PathBuf), String>> = pool.install(|| {
+            rpm_paths
+                .par_iter()
+                .map(|rpm_path| {
+                    let _permit = fd_budget.acquire();
+                    let metadata = rpm::PackageMetadata::open(rpm_path).map_err(|e| e.to_string())?;
+                    let epoch = metadata.get_epoch().unwrap_or(0).to_string();
+                    let nevra = Nevra::new(
+                        metadata.get_name().map_err(|e| e.to_string())?,
+                        &epoch,
+                        metadata.get_version().map_err(|e| e.to_string())?,
+                        metadata.get_release().map_err(|e| e.to_string())?,
+                        metadata.get_arch().map_err(|e| e.to_string())?,
+                    );
+                    Ok((nevra, rpm_path.clone()))
+                })
+                .collect()
+        });
+
+        let mut packages = BTreeMap::new();
+        for result in results {
+            let (nevra, rpm_path) = result?;
+            packages.insert(nevra, rpm_path);
+        }
+
+        Ok(Repository { packages })
+    }
+
+    /// Every indexed package, sorted by NEVRA.
+    pub fn packages(&self) -> impl Iterator<Item = (&Nevra, &PathBuf)> {
+        self.packages.iter()
+    }
+
+    /// All packages sharing the given name, in NEVRA order.
+    pub fn find_by_name<'a>(&'a self, name: &str) -> impl Iterator<Item = (&'a Nevra, &'a PathBuf)> {
+        self.packages
+            .iter()
+            .filter(move |(nevra, _)| nevra.name == name)
+    }
+
+    /// All packages of the given name whose (epoch, version) satisfies `constraint`
+    /// against `epoch`/`version`.
+    ///
+    /// Ordering is delegated entirely to [`rpm::Nevra`]'s own `Ord` impl (the same one
+    /// this repository relies on to key its `BTreeMap`) rather than a hand-rolled
+    /// version comparison: release and arch are held equal between the indexed package
+    /// and the target, so the comparison reduces to epoch and version exactly as rpm
+    /// defines it - including epoch outranking version, which a comparison over version
+    /// strings alone would miss entirely.
+    pub fn find_by_version<'a>(
+        &'a self,
+        name: &str,
+        constraint: VersionConstraint,
+        epoch: u32,
+        version: &str,
+    ) -> Vec<(&'a Nevra, &'a PathBuf)> {
+        let epoch = epoch.to_string();
+        self.find_by_name(name)
+            .filter(|(nevra, _)| {
+                let target = Nevra::new(name, &epoch, version, &nevra.release, &nevra.arch);
+                let ordering = nevra.cmp(&target);
+                match constraint {
+                    VersionConstraint::Less => ordering == std::cmp::Ordering::Less,
+                    VersionConstraint::LessEq => ordering != std::cmp::Ordering::Greater,
+                    VersionConstraint::Equal => ordering == std::cmp::Ordering::Equal,
+                    VersionConstraint::GreaterEq => ordering != std::cmp::Ordering::Less,
+                    VersionConstraint::Greater => ordering == std::cmp::Ordering::Greater,
+                }
+            })
+            .collect()
+    }
+}
+
+fn collect_rpm_paths(root: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut paths = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                dirs.push(entry_path);
+            } else if entry_path.extension().map(|ext| ext == "rpm").unwrap_or(false) {
+                paths.push(entry_path);
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+/// How many rayon worker threads to size the pool with - one per CPU, independent of
+/// any file descriptor budget.
+fn available_parallelism() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+}
+
+/// How many RPMs we're willing to have open at once while indexing.
+///
+/// Queries the process's soft RLIMIT_NOFILE and reserves a margin for file descriptors
+/// already in use (stdio, the directory walk, etc.), so the concurrently-open count can
+/// never approach the limit. A soft limit of `RLIM_INFINITY` (e.g. `ulimit -n unlimited`)
+/// can't be turned into a meaningful budget, so it falls back to a fixed cap rather than
+/// an unbounded one.
+fn max_concurrent_opens() -> usize {
+    const RESERVED_FDS: u64 = 32;
+    const FALLBACK: usize = 64;
+    const HARD_CAP: u64 = 512;
+
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+
+    let got_limit = unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) == 0 };
+    if !got_limit || limit.rlim_cur == libc::RLIM_INFINITY {
+        return FALLBACK;
+    }
+
+    limit
+        .rlim_cur
+        .saturating_sub(RESERVED_FDS)
+        .clamp(1, HARD_CAP) as usize
+}
+
+/// A counting semaphore bounding how many packages may be open at once, independent of
+/// how many rayon worker threads are running.
+struct FdBudget {
+    available: std::sync::Mutex<usize>,
+    condvar: std::sync::Condvar,
+}
+
+struct FdPermit<'a> {
+    budget: &'a FdBudget,
+}
+
+impl FdBudget {
+    fn new(permits: usize) -> Self {
+        FdBudget {
+            available: std::sync::Mutex::new(permits),
+            condvar: std::sync::Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) -> FdPermit<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.condvar.wait(available).unwrap();
+        }
+        *available -= 1;
+        FdPermit { budget: self }
+    }
+}
+
+impl Drop for FdPermit<'_> {
+    fn drop(&mut self) {
+        let mut available = self.budget.available.lock().unwrap();
+        *available += 1;
+        self.budget.condvar.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_fixture(dir: &Path, name: &str, epoch: u32, version: &str) {
+        let package = rpm::PackageBuilder::new(name, version, "MIT", "x86_64", "repository fixture package")
+            .epoch(epoch)
+            .build()
+            .unwrap();
+        let path = dir.join(format!("{name}-{version}.rpm"));
+        let mut file = fs::File::create(&path).unwrap();
+        package.write(&mut file).unwrap();
+    }
+
+    #[test]
+    fn load_indexes_by_nevra_and_supports_lookups() {
+        let tmp_dir = std::env::temp_dir().join(format!("rpmtools-repo-test-{}", std::process::id()));
+        let nested_dir = tmp_dir.join("nested");
+        fs::create_dir_all(&nested_dir).unwrap();
+
+        // One name split across the top-level directory and a nested one, to exercise
+        // the recursive walk, plus a differently-named package to exercise find_by_name.
+        write_fixture(&tmp_dir, "pkg-a", 0, "1.0.0");
+        write_fixture(&nested_dir, "pkg-a", 0, "2.0.0");
+        write_fixture(&tmp_dir, "other-pkg", 0, "1.0.0");
+
+        let repository = Repository::load(&tmp_dir).unwrap();
+        assert_eq!(repository.packages().count(), 3);
+
+        let pkg_a_versions: Vec<String> = repository
+            .find_by_name("pkg-a")
+            .map(|(nevra, _)| nevra.version.clone())
+            .collect();
+        assert_eq!(pkg_a_versions, vec!["1.0.0".to_string(), "2.0.0".to_string()]);
+
+        assert_eq!(
+            repository.find_by_version("pkg-a", VersionConstraint::Equal, 0, "1.0.0").len(),
+            1
+        );
+        assert_eq!(
+            repository.find_by_version("pkg-a", VersionConstraint::Less, 0, "1.0.0").len(),
+            0
+        );
+        assert_eq!(
+            repository.find_by_version("pkg-a", VersionConstraint::LessEq, 0, "1.0.0").len(),
+            1
+        );
+        assert_eq!(
+            repository.find_by_version("pkg-a", VersionConstraint::GreaterEq, 0, "1.0.0").len(),
+            2
+        );
+        assert_eq!(
+            repository.find_by_version("pkg-a", VersionConstraint::Greater, 0, "1.0.0").len(),
+            1
+        );
+
+        fs::remove_dir_all(&tmp_dir).unwrap();
+    }
+}
+